@@ -1,5 +1,7 @@
 //! Convert a cursor into an iterator of rects.
 
+use std::f32::consts::PI;
+
 use alacritty_terminal::vte::ansi::CursorShape;
 
 use crate::display::color::Rgb;
@@ -10,11 +12,27 @@ use crate::renderer::rects::RenderRect;
 /// Trait for conversion into the iterator.
 pub trait IntoRects {
     /// Consume the cursor for an iterator of rects.
-    fn rects(self, size_info: &SizeInfo, thickness: f32, block_replace: Option<CursorShape>) -> CursorRects;
+    fn rects(
+        self,
+        size_info: &SizeInfo,
+        thickness: f32,
+        block_replace: Option<CursorShape>,
+        prev_bounds: Option<CursorBoundingBox>,
+        undercurl: bool,
+        dashed_hollow: bool,
+    ) -> CursorRects;
 }
 
 impl IntoRects for RenderableCursor {
-    fn rects(self, size_info: &SizeInfo, thickness: f32, block_replace: Option<CursorShape>) -> CursorRects {
+    fn rects(
+        self,
+        size_info: &SizeInfo,
+        thickness: f32,
+        block_replace: Option<CursorShape>,
+        prev_bounds: Option<CursorBoundingBox>,
+        undercurl: bool,
+        dashed_hollow: bool,
+    ) -> CursorRects {
         let point = self.point();
         let x = point.column.0 as f32 * size_info.cell_width() + size_info.padding_x();
         let y = point.line as f32 * size_info.cell_height() + size_info.padding_y();
@@ -28,47 +46,130 @@ impl IntoRects for RenderableCursor {
             width *= 2.;
         }
 
-        match self.shape() {
-            let shape = match block_replace {
+        // Focused-only shapes keep their own rendering even when the unfocused
+        // hollow block would otherwise replace them.
+        let shape = match block_replace {
             None => self.shape(),
             Some(block_replace) => match self.shape() {
-                CursorShape::Beam
-                | CursorShape::Underline
-                | CursorShape::HollowBlock => self.shape(),
-                _ => block_replace
-            }
+                CursorShape::Beam | CursorShape::Underline | CursorShape::HollowBlock => {
+                    self.shape()
+                },
+                _ => block_replace,
+            },
         };
+
         match shape {
-            CursorShape::Beam => beam(x, y, height, thickness, self.color()),
-            CursorShape::Underline => underline(x, y, width, height, thickness, self.color()),
-            CursorShape::HollowBlock => hollow(x, y, width, height, thickness, self.color()),
-            RenderRect::new_cur(x, y, width, height, self.color(), 1.0).into(),
+            CursorShape::Beam => beam(x, y, height, thickness, self.color()).with_kind(CursorRectsKind::Beam),
+            CursorShape::Underline if undercurl => {
+                undercurl_wave(x, y, width, height, thickness, size_info.cell_width(), self.color())
+                    .with_kind(CursorRectsKind::Undercurl)
+            },
+            CursorShape::Underline => {
+                underline(x, y, width, height, thickness, self.color()).with_kind(CursorRectsKind::Underline)
+            },
+            CursorShape::HollowBlock if dashed_hollow => {
+                hollow_dashed(x, y, width, height, thickness, self.color())
+                    .with_kind(CursorRectsKind::HollowBlockDashed)
+            },
+            CursorShape::HollowBlock => {
+                hollow(x, y, width, height, thickness, self.color()).with_kind(CursorRectsKind::HollowBlock)
+            },
+            _ => {
+                let current = CursorBoundingBox { x, y, width, height };
+                let mut rects = smear_trail(prev_bounds, current, self.color());
+                rects.push(RenderRect::new_cur(x, y, width, height, self.color(), 1.));
+                rects.with_kind(CursorRectsKind::Block)
+            },
         }
     }
 }
 
+/// Discriminant identifying which rendering path produced a `CursorRects`.
+///
+/// Several shapes (e.g. a stationary block, a beam, a plain underline) happen
+/// to emit the same number of rects, so rect count alone can't tell a real
+/// shape change apart from a same-shape update. `interpolate` uses this to
+/// decide whether to lerp geometry or cross-fade between shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum CursorRectsKind {
+    #[default]
+    Block,
+    Beam,
+    Underline,
+    Undercurl,
+    HollowBlock,
+    HollowBlockDashed,
+}
+
+/// Axis-aligned bounding box of a cursor's block shape.
+///
+/// Captured from one frame to the next so the renderer can draw a motion
+/// smear between where the cursor was and where it is now.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorBoundingBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
 /// Cursor rect iterator.
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone)]
 pub struct CursorRects {
-    rects: [Option<RenderRect>; 4],
+    rects: Vec<RenderRect>,
     index: usize,
+    kind: CursorRectsKind,
 }
+
 impl CursorRects {
+    /// Append a rect to the end of the iterator.
+    fn push(&mut self, rect: RenderRect) {
+        self.rects.push(rect);
+    }
+
+    /// Tag these rects with the shape that produced them.
+    fn with_kind(mut self, kind: CursorRectsKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
     pub fn interpolate(&mut self, other: &Self, factor: f32, spring: f32) {
-        for (mine, theirs) in self.rects.iter_mut().zip(other.rects.iter()) {
-            *mine = match &mine {
-                Some(mine_v) => match theirs {
-                    Some(theirs_v) => Some(mine_v.interpolate(theirs_v, factor, spring)),
-                    None => None
-                }
-                None => *theirs
+        if self.kind == other.kind && self.rects.len() == other.rects.len() {
+            // Same shape: blend geometry directly, rect by rect.
+            for (mine, theirs) in self.rects.iter_mut().zip(other.rects.iter()) {
+                *mine = mine.interpolate(theirs, factor, spring);
+            }
+        } else {
+            // Different shapes (e.g. block -> beam, a `block_replace` toggle, or
+            // a same-kind transition with a different rect count, like a
+            // stationary block settling out of a smear): index-zipping would
+            // pair mismatched geometry and drop rects when one side runs out,
+            // so cross-fade between the two sets instead. The outgoing shape
+            // fades to transparent while the incoming shape fades in, rather
+            // than snapping between them.
+            let mut rects = Vec::with_capacity(self.rects.len() + other.rects.len());
+
+            for rect in &self.rects {
+                let mut rect = *rect;
+                rect.alpha *= 1. - factor;
+                rects.push(rect);
+            }
+
+            for rect in &other.rects {
+                let mut rect = *rect;
+                rect.alpha *= factor;
+                rects.push(rect);
             }
+
+            self.rects = rects;
+            self.kind = other.kind;
         }
     }
 }
+
 impl From<RenderRect> for CursorRects {
     fn from(rect: RenderRect) -> Self {
-        Self { rects: [Some(rect), None, None, None], index: 0 }
+        Self { rects: vec![rect], index: 0, kind: CursorRectsKind::default() }
     }
 }
 
@@ -76,10 +177,66 @@ impl Iterator for CursorRects {
     type Item = RenderRect;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let rect = self.rects.get_mut(self.index)?;
+        let rect = self.rects.get(self.index)?;
         self.index += 1;
-        rect.take()
+        Some(*rect)
+    }
+}
+
+/// Number of rects used to draw the cursor's motion smear.
+const SMEAR_SEGMENTS: usize = 6;
+
+/// Spring factor for the smear's leading segment, at the new cursor position.
+/// That segment is drawn at `1 - SMEAR_HEAD_SPRING` of the cursor's size.
+const SMEAR_HEAD_SPRING: f32 = 0.6;
+
+/// Spring factor for the smear's trailing segment, at the old cursor position.
+/// That segment is drawn at `1 - SMEAR_TAIL_SPRING` of the cursor's size.
+///
+/// The two factors only set segment sizes. Segment positions are spaced
+/// evenly from the old position to the new one, and the head and tail are not
+/// animated over time: the trail is built from the two positions alone and is
+/// empty once they are equal.
+const SMEAR_TAIL_SPRING: f32 = 0.25;
+
+/// Build the rects connecting a cursor's previous and current bounding boxes,
+/// so a fast jump reads as a motion trail rather than an instant snap.
+fn smear_trail(
+    prev: Option<CursorBoundingBox>,
+    current: CursorBoundingBox,
+    color: Rgb,
+) -> CursorRects {
+    let mut rects = CursorRects::default();
+
+    let prev = match prev {
+        Some(prev) if prev != current => prev,
+        _ => return rects,
+    };
+
+    for i in 0..SMEAR_SEGMENTS {
+        let t = i as f32 / (SMEAR_SEGMENTS - 1) as f32;
+
+        // Blend from the tail's factor (t = 0) to the head's (t = 1), so the
+        // segments shrink from the old position toward the new one.
+        let factor = (t * SMEAR_HEAD_SPRING + (1. - t) * SMEAR_TAIL_SPRING).min(1.);
+
+        // Interpolate directly between prev and current so the trail follows
+        // the actual travel direction, instead of the bounding box's
+        // top-left -> bottom-right diagonal (which only matches the travel
+        // direction when moving right/down).
+        let x = prev.x + (current.x - prev.x) * t;
+        let y = prev.y + (current.y - prev.y) * t;
+        let width = (current.width * (1. - factor)).max(1.);
+        let height = (current.height * (1. - factor)).max(1.);
+
+        // Alpha tapers from the tail toward the head so the trail fades out
+        // instead of ending abruptly.
+        let alpha = 0.5 * t;
+
+        rects.push(RenderRect::new_cur(x, y, width, height, color, alpha));
     }
+
+    rects
 }
 
 /// Create an iterator yielding a single beam rect.
@@ -93,22 +250,161 @@ fn underline(x: f32, y: f32, width: f32, height: f32, thickness: f32, color: Rgb
     RenderRect::new_cur(x, y, width, thickness, color, 1.).into()
 }
 
-/// Create an iterator yielding a rect for each side of the hollow block cursor.
-fn hollow(x: f32, y: f32, width: f32, height: f32, thickness: f32, color: Rgb) -> CursorRects {
-    let top_line = RenderRect::new_cur(x, y, width, thickness, color, 1.);
+/// Horizontal distance between sample points when drawing the undercurl wave.
+const UNDERCURL_STEP: f32 = 2.;
+
+/// Create an iterator yielding the cursor as a sine-wave underline, matching
+/// the wavy underline style already used for spell-check/error highlighting.
+fn undercurl_wave(
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    thickness: f32,
+    cell_width: f32,
+    color: Rgb,
+) -> CursorRects {
+    // Scale with the cell width so the wave looks consistent across font sizes.
+    let amplitude = cell_width * 0.1;
+    let wavelength = cell_width * 0.8;
+
+    let baseline = y + height - thickness;
+
+    let mut rects = CursorRects::default();
 
+    let mut sampled = 0.;
+    while sampled < width {
+        let wave_y = baseline + amplitude * (2. * PI * sampled / wavelength).sin();
+        rects.push(RenderRect::new_cur(x + sampled, wave_y, UNDERCURL_STEP, thickness, color, 1.));
+        sampled += UNDERCURL_STEP;
+    }
+
+    rects
+}
+
+/// Shared edge geometry for the hollow block cursor's four sides: the
+/// vertical sides' start y and height, the bottom edge's y, and the right
+/// edge's x.
+fn hollow_edges(x: f32, y: f32, width: f32, height: f32, thickness: f32) -> (f32, f32, f32, f32) {
     let vertical_y = y + thickness;
     let vertical_height = height - 2. * thickness;
-    let left_line = RenderRect::new_cur(x, vertical_y, thickness, vertical_height, color, 1.);
-
     let bottom_y = y + height - thickness;
-    let bottom_line = RenderRect::new_cur(x, bottom_y, width, thickness, color, 1.);
-
     let right_x = x + width - thickness;
+    (vertical_y, vertical_height, bottom_y, right_x)
+}
+
+/// Create an iterator yielding a rect for each side of the hollow block cursor.
+fn hollow(x: f32, y: f32, width: f32, height: f32, thickness: f32, color: Rgb) -> CursorRects {
+    let (vertical_y, vertical_height, bottom_y, right_x) = hollow_edges(x, y, width, height, thickness);
+
+    let top_line = RenderRect::new_cur(x, y, width, thickness, color, 1.);
+    let bottom_line = RenderRect::new_cur(x, bottom_y, width, thickness, color, 1.);
+    let left_line = RenderRect::new_cur(x, vertical_y, thickness, vertical_height, color, 1.);
     let right_line = RenderRect::new_cur(right_x, vertical_y, thickness, vertical_height, color, 1.);
 
-    CursorRects {
-        rects: [Some(top_line), Some(bottom_line), Some(left_line), Some(right_line)],
-        index: 0,
+    let mut rects = CursorRects::default();
+    rects.push(top_line);
+    rects.push(bottom_line);
+    rects.push(left_line);
+    rects.push(right_line);
+    rects
+}
+
+/// Length of each dash segment in the unfocused hollow block outline.
+const DASH_LENGTH: f32 = 4.;
+
+/// Create an iterator yielding a dashed rect for each side of the hollow
+/// block cursor, so an unfocused cursor is distinguishable from a focused one
+/// at a glance.
+fn hollow_dashed(x: f32, y: f32, width: f32, height: f32, thickness: f32, color: Rgb) -> CursorRects {
+    let (vertical_y, vertical_height, bottom_y, right_x) = hollow_edges(x, y, width, height, thickness);
+
+    let mut rects = CursorRects::default();
+    dash_horizontal(&mut rects, x, y, width, thickness, color);
+    dash_horizontal(&mut rects, x, bottom_y, width, thickness, color);
+    dash_vertical(&mut rects, x, vertical_y, vertical_height, thickness, color);
+    dash_vertical(&mut rects, right_x, vertical_y, vertical_height, thickness, color);
+    rects
+}
+
+/// Split a horizontal edge into alternating on/off dash segments, pushing
+/// only the "on" segments.
+fn dash_horizontal(rects: &mut CursorRects, x: f32, y: f32, width: f32, thickness: f32, color: Rgb) {
+    let mut sampled = 0.;
+    let mut on = true;
+    while sampled < width {
+        let segment = (width - sampled).min(DASH_LENGTH);
+        if on {
+            rects.push(RenderRect::new_cur(x + sampled, y, segment, thickness, color, 1.));
+        }
+        sampled += segment;
+        on = !on;
+    }
+}
+
+/// Split a vertical edge into alternating on/off dash segments, pushing only
+/// the "on" segments.
+fn dash_vertical(rects: &mut CursorRects, x: f32, y: f32, height: f32, thickness: f32, color: Rgb) {
+    let mut sampled = 0.;
+    let mut on = true;
+    while sampled < height {
+        let segment = (height - sampled).min(DASH_LENGTH);
+        if on {
+            rects.push(RenderRect::new_cur(x, y + sampled, thickness, segment, color, 1.));
+        }
+        sampled += segment;
+        on = !on;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(x: f32, y: f32, width: f32, height: f32) -> CursorBoundingBox {
+        CursorBoundingBox { x, y, width, height }
+    }
+
+    #[test]
+    fn smear_trail_follows_leftward_motion() {
+        let prev = bounds(50., 0., 10., 20.);
+        let current = bounds(0., 0., 10., 20.);
+
+        let rects: Vec<_> = smear_trail(Some(prev), current, Rgb::default()).collect();
+
+        assert_eq!(rects.len(), SMEAR_SEGMENTS);
+        assert_eq!(rects.first().unwrap().x, prev.x);
+        assert_eq!(rects.last().unwrap().x, current.x);
+    }
+
+    #[test]
+    fn smear_trail_follows_upward_motion() {
+        let prev = bounds(0., 50., 10., 20.);
+        let current = bounds(0., 0., 10., 20.);
+
+        let rects: Vec<_> = smear_trail(Some(prev), current, Rgb::default()).collect();
+
+        assert_eq!(rects.len(), SMEAR_SEGMENTS);
+        assert_eq!(rects.first().unwrap().y, prev.y);
+        assert_eq!(rects.last().unwrap().y, current.y);
+    }
+
+    #[test]
+    fn smear_trail_tapers_alpha_from_tail_to_head() {
+        let prev = bounds(0., 0., 10., 20.);
+        let current = bounds(20., 0., 10., 20.);
+
+        let rects: Vec<_> = smear_trail(Some(prev), current, Rgb::default()).collect();
+
+        assert_eq!(rects.first().unwrap().alpha, 0.);
+        assert!(rects.last().unwrap().alpha > rects.first().unwrap().alpha);
+    }
+
+    #[test]
+    fn smear_trail_empty_when_stationary() {
+        let stationary = bounds(0., 0., 10., 20.);
+
+        assert_eq!(smear_trail(Some(stationary), stationary, Rgb::default()).count(), 0);
+        assert_eq!(smear_trail(None, stationary, Rgb::default()).count(), 0);
     }
 }